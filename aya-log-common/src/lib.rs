@@ -1,13 +1,18 @@
 #![no_std]
 
-use core::{cmp, mem, ptr, slice};
+use core::{cmp, mem, num::NonZeroUsize, ptr, slice};
+
+use num_enum::IntoPrimitive;
 
 pub const LOG_BUF_CAPACITY: usize = 8192;
 
 pub const LOG_FIELDS: usize = 6;
 
-#[repr(usize)]
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+/// The wire width of a [`TagLenValue`]'s length field.
+pub type LogValueLength = u16;
+
+#[repr(u8)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, IntoPrimitive)]
 pub enum Level {
     /// The "error" level.
     ///
@@ -31,8 +36,8 @@ pub enum Level {
     Trace,
 }
 
-#[repr(usize)]
-#[derive(Copy, Clone, Debug)]
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, IntoPrimitive)]
 pub enum RecordField {
     Target = 1,
     Level,
@@ -42,8 +47,8 @@ pub enum RecordField {
     NumArgs,
 }
 
-#[repr(usize)]
-#[derive(Copy, Clone, Debug)]
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, IntoPrimitive)]
 pub enum ArgType {
     I8,
     I16,
@@ -62,8 +67,10 @@ pub enum ArgType {
 
     ArrU8Len16,
     ArrU16Len8,
+    ArrU8Len6,
 
     Str,
+    Bytes,
 }
 
 #[cfg(feature = "userspace")]
@@ -73,10 +80,85 @@ mod userspace {
     unsafe impl aya::Pod for RecordField {}
     unsafe impl aya::Pod for ArgType {}
     unsafe impl aya::Pod for DisplayHint {}
+
+    impl TryFrom<u8> for RecordField {
+        type Error = ();
+
+        fn try_from(v: u8) -> Result<Self, Self::Error> {
+            match v {
+                x if x == RecordField::Target as u8 => Ok(RecordField::Target),
+                x if x == RecordField::Level as u8 => Ok(RecordField::Level),
+                x if x == RecordField::Module as u8 => Ok(RecordField::Module),
+                x if x == RecordField::File as u8 => Ok(RecordField::File),
+                x if x == RecordField::Line as u8 => Ok(RecordField::Line),
+                x if x == RecordField::NumArgs as u8 => Ok(RecordField::NumArgs),
+                _ => Err(()),
+            }
+        }
+    }
+
+    impl TryFrom<u8> for ArgType {
+        type Error = ();
+
+        fn try_from(v: u8) -> Result<Self, Self::Error> {
+            match v {
+                x if x == ArgType::I8 as u8 => Ok(ArgType::I8),
+                x if x == ArgType::I16 as u8 => Ok(ArgType::I16),
+                x if x == ArgType::I32 as u8 => Ok(ArgType::I32),
+                x if x == ArgType::I64 as u8 => Ok(ArgType::I64),
+                x if x == ArgType::Isize as u8 => Ok(ArgType::Isize),
+                x if x == ArgType::U8 as u8 => Ok(ArgType::U8),
+                x if x == ArgType::U16 as u8 => Ok(ArgType::U16),
+                x if x == ArgType::U32 as u8 => Ok(ArgType::U32),
+                x if x == ArgType::U64 as u8 => Ok(ArgType::U64),
+                x if x == ArgType::Usize as u8 => Ok(ArgType::Usize),
+                x if x == ArgType::F32 as u8 => Ok(ArgType::F32),
+                x if x == ArgType::F64 as u8 => Ok(ArgType::F64),
+                x if x == ArgType::ArrU8Len16 as u8 => Ok(ArgType::ArrU8Len16),
+                x if x == ArgType::ArrU16Len8 as u8 => Ok(ArgType::ArrU16Len8),
+                x if x == ArgType::ArrU8Len6 as u8 => Ok(ArgType::ArrU8Len6),
+                x if x == ArgType::Str as u8 => Ok(ArgType::Str),
+                x if x == ArgType::Bytes as u8 => Ok(ArgType::Bytes),
+                _ => Err(()),
+            }
+        }
+    }
+
+    impl TryFrom<u8> for DisplayHint {
+        type Error = ();
+
+        fn try_from(v: u8) -> Result<Self, Self::Error> {
+            match v {
+                x if x == DisplayHint::Default as u8 => Ok(DisplayHint::Default),
+                x if x == DisplayHint::LowerHex as u8 => Ok(DisplayHint::LowerHex),
+                x if x == DisplayHint::UpperHex as u8 => Ok(DisplayHint::UpperHex),
+                x if x == DisplayHint::IPv4 as u8 => Ok(DisplayHint::IPv4),
+                x if x == DisplayHint::IPv6 as u8 => Ok(DisplayHint::IPv6),
+                x if x == DisplayHint::LowerMac as u8 => Ok(DisplayHint::LowerMac),
+                x if x == DisplayHint::UpperMac as u8 => Ok(DisplayHint::UpperMac),
+                _ => Err(()),
+            }
+        }
+    }
+
+    impl TryFrom<u8> for Level {
+        type Error = ();
+
+        fn try_from(v: u8) -> Result<Self, Self::Error> {
+            match v {
+                x if x == Level::Error as u8 => Ok(Level::Error),
+                x if x == Level::Warn as u8 => Ok(Level::Warn),
+                x if x == Level::Info as u8 => Ok(Level::Info),
+                x if x == Level::Debug as u8 => Ok(Level::Debug),
+                x if x == Level::Trace as u8 => Ok(Level::Trace),
+                _ => Err(()),
+            }
+        }
+    }
 }
 
 /// All display hints
-#[repr(usize)]
+#[repr(u8)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum DisplayHint {
     /// Default string representation.
@@ -89,6 +171,10 @@ pub enum DisplayHint {
     IPv4,
     /// `:ipv6`, `:IPv6`
     IPv6,
+    /// `:mac`
+    LowerMac,
+    /// `:MAC`
+    UpperMac,
 }
 
 pub struct TagLenValue<'a, T> {
@@ -99,46 +185,57 @@ pub struct TagLenValue<'a, T> {
 
 impl<'a, T> TagLenValue<'a, T>
 where
-    T: Copy,
+    T: Copy + Into<u8>,
 {
     #[inline(always)]
     pub fn new(tag: T, value: &'a [u8], hint: DisplayHint) -> TagLenValue<'a, T> {
         TagLenValue { tag, value, hint }
     }
 
-    pub(crate) fn write(&self, mut buf: &mut [u8]) -> Result<usize, ()> {
-        let size = mem::size_of::<T>()
-            + mem::size_of::<DisplayHint>()
-            + mem::size_of::<usize>()
-            + self.value.len();
-        if buf.len() < size {
-            return Err(());
-        }
+    pub(crate) fn write(&self, buf: &mut [u8]) -> Option<NonZeroUsize> {
+        write(self.tag.into(), self.hint as u8, self.value, buf)
+    }
+}
 
-        unsafe { ptr::write_unaligned(buf.as_mut_ptr() as *mut _, self.tag) };
-        buf = &mut buf[mem::size_of::<T>()..];
+/// Writes a single `tag`/`hint`/`value` triple to `buf` as one byte of tag,
+/// one byte of hint, a [`LogValueLength`] of length, followed by the value
+/// bytes. Returns the total number of bytes written, which is never zero.
+fn write(tag: u8, hint: u8, value: &[u8], mut buf: &mut [u8]) -> Option<NonZeroUsize> {
+    let value_len: LogValueLength = value.len().try_into().ok()?;
 
-        unsafe { ptr::write_unaligned(buf.as_mut_ptr() as *mut _, self.hint) };
-        buf = &mut buf[mem::size_of::<usize>()..];
+    let size = mem::size_of_val(&tag)
+        + mem::size_of_val(&hint)
+        + mem::size_of::<LogValueLength>()
+        + value.len();
+    if buf.len() < size {
+        return None;
+    }
 
-        unsafe { ptr::write_unaligned(buf.as_mut_ptr() as *mut _, self.value.len()) };
-        buf = &mut buf[mem::size_of::<usize>()..];
+    buf[0] = tag;
+    buf = &mut buf[mem::size_of_val(&tag)..];
 
-        let len = cmp::min(buf.len(), self.value.len());
-        buf[..len].copy_from_slice(&self.value[..len]);
-        Ok(size)
-    }
+    buf[0] = hint;
+    buf = &mut buf[mem::size_of_val(&hint)..];
+
+    unsafe { ptr::write_unaligned(buf.as_mut_ptr() as *mut _, value_len) };
+    buf = &mut buf[mem::size_of::<LogValueLength>()..];
+
+    let len = cmp::min(buf.len(), value.len());
+    buf[..len].copy_from_slice(&value[..len]);
+
+    // SAFETY: `size` always includes at least the tag and hint bytes, so it
+    // is never zero.
+    Some(unsafe { NonZeroUsize::new_unchecked(size) })
 }
 
 pub trait WriteToBuf {
-    #[allow(clippy::result_unit_err)]
-    fn write(&self, buf: &mut [u8], hint: DisplayHint) -> Result<usize, ()>;
+    fn write(&self, buf: &mut [u8], hint: DisplayHint) -> Option<NonZeroUsize>;
 }
 
 macro_rules! impl_write_to_buf {
     ($type:ident, $arg_type:expr) => {
         impl WriteToBuf for $type {
-            fn write(&self, buf: &mut [u8], hint: DisplayHint) -> Result<usize, ()> {
+            fn write(&self, buf: &mut [u8], hint: DisplayHint) -> Option<NonZeroUsize> {
                 TagLenValue::<ArgType>::new($arg_type, &self.to_ne_bytes(), hint).write(buf)
             }
         }
@@ -161,13 +258,13 @@ impl_write_to_buf!(f32, ArgType::F32);
 impl_write_to_buf!(f64, ArgType::F64);
 
 impl WriteToBuf for [u8; 16] {
-    fn write(&self, buf: &mut [u8], hint: DisplayHint) -> Result<usize, ()> {
+    fn write(&self, buf: &mut [u8], hint: DisplayHint) -> Option<NonZeroUsize> {
         TagLenValue::<ArgType>::new(ArgType::ArrU8Len16, self, hint).write(buf)
     }
 }
 
 impl WriteToBuf for [u16; 8] {
-    fn write(&self, buf: &mut [u8], hint: DisplayHint) -> Result<usize, ()> {
+    fn write(&self, buf: &mut [u8], hint: DisplayHint) -> Option<NonZeroUsize> {
         let len = self.len() * 2;
         let ptr = self.as_ptr().cast::<u8>();
         let bytes = unsafe { slice::from_raw_parts(ptr, len) };
@@ -175,13 +272,111 @@ impl WriteToBuf for [u16; 8] {
     }
 }
 
+impl WriteToBuf for [u8; 6] {
+    fn write(&self, buf: &mut [u8], hint: DisplayHint) -> Option<NonZeroUsize> {
+        TagLenValue::<ArgType>::new(ArgType::ArrU8Len6, self, hint).write(buf)
+    }
+}
+
 impl WriteToBuf for str {
-    fn write(&self, buf: &mut [u8], hint: DisplayHint) -> Result<usize, ()> {
+    fn write(&self, buf: &mut [u8], hint: DisplayHint) -> Option<NonZeroUsize> {
         TagLenValue::<ArgType>::new(ArgType::Str, self.as_bytes(), hint).write(buf)
     }
 }
 
-#[allow(clippy::result_unit_err)]
+impl WriteToBuf for [u8] {
+    fn write(&self, buf: &mut [u8], hint: DisplayHint) -> Option<NonZeroUsize> {
+        TagLenValue::<ArgType>::new(ArgType::Bytes, self, hint).write(buf)
+    }
+}
+
+/// Selects the default (hint-less) rendering of a value. Implemented for
+/// every type that supports `WriteToBuf`, so the `log!` macro can always
+/// fall back to it for literal format-string fragments.
+pub trait DefaultFormatter: WriteToBuf {
+    fn write_default(&self, buf: &mut [u8]) -> Option<NonZeroUsize> {
+        WriteToBuf::write(self, buf, DisplayHint::Default)
+    }
+}
+
+impl<T: WriteToBuf + ?Sized> DefaultFormatter for T {}
+
+/// Selects the `:x` rendering of a value. Only implemented for types that
+/// have a sensible hex representation, so `{:x}` on e.g. an `f64` is a
+/// compile error.
+pub trait LowerHexFormatter: WriteToBuf {
+    fn write_lower_hex(&self, buf: &mut [u8]) -> Option<NonZeroUsize> {
+        WriteToBuf::write(self, buf, DisplayHint::LowerHex)
+    }
+}
+
+/// Selects the `:X` rendering of a value. See [`LowerHexFormatter`].
+pub trait UpperHexFormatter: WriteToBuf {
+    fn write_upper_hex(&self, buf: &mut [u8]) -> Option<NonZeroUsize> {
+        WriteToBuf::write(self, buf, DisplayHint::UpperHex)
+    }
+}
+
+macro_rules! impl_hex_formatter {
+    ($type:ident) => {
+        impl LowerHexFormatter for $type {}
+        impl UpperHexFormatter for $type {}
+    };
+}
+
+impl_hex_formatter!(i8);
+impl_hex_formatter!(i16);
+impl_hex_formatter!(i32);
+impl_hex_formatter!(i64);
+impl_hex_formatter!(isize);
+impl_hex_formatter!(u8);
+impl_hex_formatter!(u16);
+impl_hex_formatter!(u32);
+impl_hex_formatter!(u64);
+impl_hex_formatter!(usize);
+
+impl LowerHexFormatter for [u8] {}
+impl UpperHexFormatter for [u8] {}
+
+/// Selects the `:ipv4` rendering of a value. Only implemented for `u32`.
+pub trait IPv4Formatter: WriteToBuf {
+    fn write_ipv4(&self, buf: &mut [u8]) -> Option<NonZeroUsize> {
+        WriteToBuf::write(self, buf, DisplayHint::IPv4)
+    }
+}
+
+impl IPv4Formatter for u32 {}
+
+/// Selects the `:ipv6` rendering of a value. Only implemented for
+/// `[u8; 16]`.
+pub trait IPv6Formatter: WriteToBuf {
+    fn write_ipv6(&self, buf: &mut [u8]) -> Option<NonZeroUsize> {
+        WriteToBuf::write(self, buf, DisplayHint::IPv6)
+    }
+}
+
+impl IPv6Formatter for [u8; 16] {}
+
+/// Selects the `:mac` rendering of a value. Only implemented for
+/// `[u8; 6]`.
+pub trait LowerMacFormatter: WriteToBuf {
+    fn write_lower_mac(&self, buf: &mut [u8]) -> Option<NonZeroUsize> {
+        WriteToBuf::write(self, buf, DisplayHint::LowerMac)
+    }
+}
+
+impl LowerMacFormatter for [u8; 6] {}
+
+/// Selects the `:MAC` rendering of a value. Only implemented for
+/// `[u8; 6]`.
+pub trait UpperMacFormatter: WriteToBuf {
+    fn write_upper_mac(&self, buf: &mut [u8]) -> Option<NonZeroUsize> {
+        WriteToBuf::write(self, buf, DisplayHint::UpperMac)
+    }
+}
+
+impl UpperMacFormatter for [u8; 6] {}
+
 #[doc(hidden)]
 #[inline(always)]
 pub fn write_record_header(
@@ -192,7 +387,7 @@ pub fn write_record_header(
     file: &str,
     line: u32,
     num_args: usize,
-) -> Result<usize, ()> {
+) -> Option<NonZeroUsize> {
     let mut size = 0;
     for attr in [
         TagLenValue::<RecordField>::new(
@@ -202,7 +397,7 @@ pub fn write_record_header(
         ),
         TagLenValue::<RecordField>::new(
             RecordField::Level,
-            &(level as usize).to_ne_bytes(),
+            &[level.into()],
             DisplayHint::Default,
         ),
         TagLenValue::<RecordField>::new(
@@ -222,8 +417,8 @@ pub fn write_record_header(
             DisplayHint::Default,
         ),
     ] {
-        size += attr.write(&mut buf[size..])?;
+        size += attr.write(&mut buf[size..])?.get();
     }
 
-    Ok(size)
+    NonZeroUsize::new(size)
 }
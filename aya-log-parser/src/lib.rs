@@ -5,6 +5,10 @@ use aya_log_common::DisplayHint;
 /// A parsed formatting parameter (contents of `{` `}` block).
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Parameter {
+    /// The explicit argument index, e.g. the `0` in `{0}`/`{0:x}`. `None`
+    /// means the parameter should consume the next argument in sequence,
+    /// exactly like `std::fmt`.
+    pub index: Option<usize>,
     /// The display hint, e.g. ':ipv4', ':IPv4'.
     pub hint: DisplayHint,
 }
@@ -65,16 +69,34 @@ fn parse_display_hint(s: &str) -> Result<DisplayHint, Cow<'static, str>> {
         "IPv4" => DisplayHint::IPv4,
         "ipv6" => DisplayHint::IPv6,
         "IPv6" => DisplayHint::IPv6,
+        "mac" => DisplayHint::LowerMac,
+        "MAC" => DisplayHint::UpperMac,
         _ => return Err(format!("unknown display hint: {:?}", s).into()),
     })
 }
 
 /// Parse `Param` from `&str`
 ///
-/// * example `input`: `:hint` (note: no curly braces)
+/// * example `input`: `:hint`, `0`, or `0:hint` (note: no curly braces)
 fn parse_param(mut input: &str) -> Result<Parameter, Cow<'static, str>> {
     const HINT_PREFIX: &str = ":";
 
+    // First, an optional explicit argument index.
+    let digits = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let index = if digits > 0 {
+        let (index, rest) = input.split_at(digits);
+        input = rest;
+        Some(
+            index
+                .parse()
+                .map_err(|_| format!("invalid argument index {:?}", index))?,
+        )
+    } else {
+        None
+    };
+
     // Then, optional hint
     let mut hint = DisplayHint::Default;
 
@@ -90,7 +112,7 @@ fn parse_param(mut input: &str) -> Result<Parameter, Cow<'static, str>> {
         return Err(format!("unexpected content {:?} in format string", input).into());
     }
 
-    Ok(Parameter { hint })
+    Ok(Parameter { index, hint })
 }
 
 pub fn parse<'a>(format_string: &'a str) -> Result<Vec<Fragment<'a>>, Cow<'static, str>> {
@@ -148,36 +170,71 @@ mod test {
     #[test]
     fn test_parse() {
         assert_eq!(
-            parse("foo {} bar {:x} test {:X} ayy {:ipv4} lmao {:IPv4} hello {:ipv6} world {:IPv6}"),
+            parse("foo {} bar {:x} test {:X} ayy {:ipv4} lmao {:IPv4} hello {:ipv6} world {:IPv6} mac {:mac} MAC {:MAC}"),
             Ok(vec![
                 Fragment::Literal("foo ".into()),
                 Fragment::Parameter(Parameter {
+                    index: None,
                     hint: DisplayHint::Default
                 }),
                 Fragment::Literal(" bar ".into()),
                 Fragment::Parameter(Parameter {
+                    index: None,
                     hint: DisplayHint::LowerHex
                 }),
                 Fragment::Literal(" test ".into()),
                 Fragment::Parameter(Parameter {
+                    index: None,
                     hint: DisplayHint::UpperHex
                 }),
                 Fragment::Literal(" ayy ".into()),
                 Fragment::Parameter(Parameter {
+                    index: None,
                     hint: DisplayHint::IPv4
                 }),
                 Fragment::Literal(" lmao ".into()),
                 Fragment::Parameter(Parameter {
+                    index: None,
                     hint: DisplayHint::IPv4
                 }),
                 Fragment::Literal(" hello ".into()),
                 Fragment::Parameter(Parameter {
+                    index: None,
                     hint: DisplayHint::IPv6
                 }),
                 Fragment::Literal(" world ".into()),
                 Fragment::Parameter(Parameter {
+                    index: None,
                     hint: DisplayHint::IPv6
                 }),
+                Fragment::Literal(" mac ".into()),
+                Fragment::Parameter(Parameter {
+                    index: None,
+                    hint: DisplayHint::LowerMac
+                }),
+                Fragment::Literal(" MAC ".into()),
+                Fragment::Parameter(Parameter {
+                    index: None,
+                    hint: DisplayHint::UpperMac
+                }),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_positional() {
+        assert_eq!(
+            parse("{0} == {0:x}"),
+            Ok(vec![
+                Fragment::Parameter(Parameter {
+                    index: Some(0),
+                    hint: DisplayHint::Default
+                }),
+                Fragment::Literal(" == ".into()),
+                Fragment::Parameter(Parameter {
+                    index: Some(0),
+                    hint: DisplayHint::LowerHex
+                }),
             ])
         );
     }
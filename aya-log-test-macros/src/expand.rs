@@ -3,6 +3,7 @@ use std::borrow::Cow;
 use proc_macro2::TokenStream;
 use quote::quote;
 use syn::{
+    custom_keyword,
     parse::{Parse, ParseStream},
     parse_str,
     punctuated::Punctuated,
@@ -12,8 +13,11 @@ use syn::{
 use aya_log_common::DisplayHint;
 use aya_log_parser::{parse, Fragment};
 
+custom_keyword!(target);
+
 pub(crate) struct LogArgs {
     pub(crate) buf: Expr,
+    pub(crate) target: Option<LitStr>,
     pub(crate) format_string: LitStr,
     pub(crate) formatting_args: Option<Punctuated<Expr, Token![,]>>,
 }
@@ -22,6 +26,19 @@ impl Parse for LogArgs {
     fn parse(input: ParseStream) -> Result<Self> {
         let buf: Expr = input.parse()?;
         input.parse::<Token![,]>()?;
+
+        // An optional `target: "...",` leading argument, like the standard
+        // `log` crate's macros accept.
+        let target = if input.peek(target) && input.peek2(Token![:]) {
+            input.parse::<target>()?;
+            input.parse::<Token![:]>()?;
+            let target: LitStr = input.parse()?;
+            input.parse::<Token![,]>()?;
+            Some(target)
+        } else {
+            None
+        };
+
         let format_string: LitStr = input.parse()?;
         let formatting_args: Option<Punctuated<Expr, Token![,]>> = if input.is_empty() {
             None
@@ -32,6 +49,7 @@ impl Parse for LogArgs {
 
         Ok(Self {
             buf,
+            target,
             format_string,
             formatting_args,
         })
@@ -42,13 +60,25 @@ fn string_to_expr(s: Cow<str>) -> Result<Expr> {
     parse_str(&format!("\"{}\"", s))
 }
 
-fn hint_to_expr(hint: DisplayHint) -> Result<Expr> {
+/// The name of the hint-specific formatter method selected by a display
+/// hint, e.g. `:ipv6` resolves to `write_ipv6`. Each of these methods comes
+/// from a trait (`IPv6Formatter`, etc.) that's only implemented for types
+/// that can sensibly be rendered with that hint, so a mismatch (e.g.
+/// `:ipv6` on a `u64`) is a compile error rather than a runtime one.
+///
+/// These are invoked with method-call syntax (`value.write_ipv6(buf)`)
+/// rather than a fully-qualified path so that autoref/autoderef resolves
+/// the receiver regardless of whether `value` is already a reference
+/// (`&str`, `&[u8]`) or an owned, `Copy` value (`u32`, `[u8; 16]`).
+fn hint_to_method(hint: DisplayHint) -> TokenStream {
     match hint {
-        DisplayHint::Default => parse_str("::aya_log_common::DisplayHint::Default"),
-        DisplayHint::LowerHex => parse_str("::aya_log_common::DisplayHint::LowerHex"),
-        DisplayHint::UpperHex => parse_str("::aya_log_common::DisplayHint::UpperHex"),
-        DisplayHint::IPv4 => parse_str("::aya_log_common::DisplayHint::IPv4"),
-        DisplayHint::IPv6 => parse_str("::aya_log_common::DisplayHint::IPv6"),
+        DisplayHint::Default => quote! { write_default },
+        DisplayHint::LowerHex => quote! { write_lower_hex },
+        DisplayHint::UpperHex => quote! { write_upper_hex },
+        DisplayHint::IPv4 => quote! { write_ipv4 },
+        DisplayHint::IPv6 => quote! { write_ipv6 },
+        DisplayHint::LowerMac => quote! { write_lower_mac },
+        DisplayHint::UpperMac => quote! { write_upper_mac },
     }
 }
 
@@ -58,54 +88,178 @@ pub(crate) fn log(args: LogArgs) -> Result<TokenStream> {
     let fragments = parse(&format_string_val)
         .map_err(|_| Error::new(format_string.span(), "failed to parse format string"))?;
 
+    let num_provided = args.formatting_args.as_ref().map_or(0, |args| args.len());
+
+    // Bind each originally supplied argument to a local once, so that a
+    // `Parameter` referencing the same index more than once (e.g.
+    // `"{0} == {0:x}"`) evaluates the underlying expression exactly once,
+    // exactly like `std::fmt`/`format_args!` does.
+    let arg_idents: Vec<_> = (0..num_provided)
+        .map(|i| quote::format_ident!("__arg{i}"))
+        .collect();
+    let arg_bindings: Vec<_> = args
+        .formatting_args
+        .as_ref()
+        .into_iter()
+        .flatten()
+        .zip(&arg_idents)
+        .map(|(expr, ident)| quote! { let #ident = &(#expr); })
+        .collect();
+
+    let mut used = vec![false; num_provided];
     let mut values = Vec::new();
-    let mut hints = Vec::new();
-    let mut arg_i = 0;
+    let mut methods = Vec::new();
+    // The running index consumed by parameters with no explicit index,
+    // e.g. the `{}` in `"{1} {}"` takes argument 0, exactly like `std::fmt`.
+    let mut next_index = 0;
     for fragment in fragments {
         match fragment {
             Fragment::Literal(s) => {
-                values.push(string_to_expr(s)?);
-                hints.push(hint_to_expr(DisplayHint::Default)?);
+                let s = string_to_expr(s)?;
+                values.push(quote! { #s });
+                methods.push(hint_to_method(DisplayHint::Default));
             }
             Fragment::Parameter(p) => {
-                let arg = match args.formatting_args {
-                    Some(ref args) => args[arg_i].clone(),
-                    None => return Err(Error::new(format_string.span(), "no arguments provided")),
-                };
-                values.push(arg);
-                hints.push(hint_to_expr(p.hint)?);
-                arg_i += 1;
+                let index = p.index.unwrap_or_else(|| {
+                    let index = next_index;
+                    next_index += 1;
+                    index
+                });
+                if index >= num_provided {
+                    return Err(Error::new(
+                        format_string.span(),
+                        format!(
+                            "invalid reference to positional argument {index} ({num_provided} argument(s) were provided)"
+                        ),
+                    ));
+                }
+                used[index] = true;
+                let ident = &arg_idents[index];
+                values.push(quote! { #ident });
+                methods.push(hint_to_method(p.hint));
             }
         }
     }
+
+    let unused: Vec<_> = used
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &used)| (!used).then_some(i))
+        .collect();
+    if let [index] = unused[..] {
+        return Err(Error::new(
+            format_string.span(),
+            format!("argument {index} never used"),
+        ));
+    } else if !unused.is_empty() {
+        let indices = unused
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(Error::new(
+            format_string.span(),
+            format!("arguments {indices} never used"),
+        ));
+    }
+
     let num_args = values.len();
 
     let values_iter = values.iter();
-    let hints_iter = hints.iter();
+    let methods_iter = methods.iter();
 
     let buf = args.buf;
+    let target = match &args.target {
+        Some(target) => quote! { #target },
+        None => quote! { ::core::module_path!() },
+    };
 
     Ok(quote! {
         {
-            if let Ok(header_len) = ::aya_log_common::write_record_header(
+            // Bring the hint-specific formatter traits into scope (as `_`
+            // so an unused one doesn't warn) so the method calls below
+            // resolve via ordinary autoref/autoderef, exactly like any
+            // other trait method call.
+            use ::aya_log_common::{
+                DefaultFormatter as _, IPv4Formatter as _, IPv6Formatter as _,
+                LowerHexFormatter as _, LowerMacFormatter as _, UpperHexFormatter as _,
+                UpperMacFormatter as _,
+            };
+
+            #(#arg_bindings)*
+
+            if let Some(header_len) = ::aya_log_common::write_record_header(
                 &mut #buf,
-                "test",
+                #target,
                 ::aya_log_common::Level::Info,
-                "test",
-                "test.rs",
-                123,
+                ::core::module_path!(),
+                ::core::file!(),
+                ::core::line!(),
                 #num_args
             ) {
-                let mut record_len = header_len;
+                let mut record_len = header_len.get();
 
-                use ::aya_log_common::WriteToBuf;
                 #(
                     if record_len >= #buf.len() {
                         return ();
                     }
-                    record_len += { #values_iter }.write(&mut #buf[record_len..], #hints_iter).unwrap();
+                    record_len += match (#values_iter).#methods_iter(&mut #buf[record_len..]) {
+                        Some(len) => len.get(),
+                        None => return (),
+                    };
                 )*
             }
         }
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn expand(tokens: TokenStream) -> Result<TokenStream> {
+        syn::parse2::<LogArgs>(tokens).and_then(log)
+    }
+
+    // Regression test: a prior version wrapped already-reference-typed
+    // arguments (e.g. a `name: &str`) in an extra `&` and dispatched
+    // through a fully-qualified trait function path, which broke type
+    // inference for every `&str`/`&[u8]` argument and for any literal text
+    // in the format string (`string_to_expr` always produces a `&str`).
+    // Dispatching through method-call syntax instead lets autoref/autoderef
+    // paper over the difference, regardless of whether the argument is a
+    // reference or an owned `Copy` value.
+    #[test]
+    fn reference_and_owned_args_use_method_call_syntax() {
+        let tokens = expand(quote! {
+            buf, "name: {}, x = {:x}", name, value
+        })
+        .unwrap();
+        let rendered = tokens.to_string();
+        assert!(rendered.contains("write_default"));
+        assert!(rendered.contains("write_lower_hex"));
+        assert!(!rendered.contains("Formatter"));
+    }
+
+    // Regression test: referencing the same positional argument twice (e.g.
+    // `"{0} == {0:x}"`) must evaluate the underlying expression exactly
+    // once, exactly like `std::fmt`.
+    #[test]
+    fn repeated_positional_argument_is_evaluated_once() {
+        let tokens = expand(quote! {
+            buf, "{0} == {0:x}", side_effect()
+        })
+        .unwrap();
+        let rendered = tokens.to_string();
+        assert_eq!(rendered.matches("side_effect").count(), 1);
+    }
+
+    #[test]
+    fn unused_argument_is_rejected() {
+        let err = expand(quote! {
+            buf, "{}", a, b
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("never used"));
+    }
+}
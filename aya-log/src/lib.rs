@@ -0,0 +1,250 @@
+//! Userspace decoding for records emitted by `aya-log-ebpf`.
+//!
+//! Programs that use the `aya-log-ebpf` macros serialize each log record
+//! using the `TagLenValue` wire format defined in `aya-log-common`. This
+//! crate reads that wire format back out of the buffer the eBPF program
+//! wrote to and reassembles the formatted message, so it can be handed to
+//! the `log` crate (or printed directly) on the userspace side.
+
+use std::{mem, net::{Ipv4Addr, Ipv6Addr}, str};
+
+use aya_log_common::{ArgType, DisplayHint, Level, LogValueLength, RecordField};
+
+/// Errors that can occur while decoding a record.
+#[derive(Debug)]
+pub enum Error {
+    /// The record is truncated or malformed: the buffer ended before a
+    /// complete field could be read, a value has the wrong size for its
+    /// type, or a header field is missing.
+    InvalidRecord,
+    /// A field tag, display hint or level byte did not match any known
+    /// `RecordField`/`ArgType`/`DisplayHint`/`Level` value.
+    UnknownTag,
+    /// A string argument was not valid UTF-8.
+    InvalidString(str::Utf8Error),
+    /// `hint` cannot be applied to `arg_type`.
+    InvalidDisplayHint {
+        hint: DisplayHint,
+        arg_type: ArgType,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidRecord => write!(f, "invalid record: truncated or malformed"),
+            Error::UnknownTag => write!(f, "invalid record: unknown tag"),
+            Error::InvalidString(e) => write!(f, "invalid string: {e}"),
+            Error::InvalidDisplayHint { hint, arg_type } => {
+                write!(f, "display hint {hint:?} cannot be applied to {arg_type:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A fully decoded log record.
+#[derive(Debug)]
+pub struct Record {
+    pub target: String,
+    pub level: Level,
+    pub module: String,
+    pub file: String,
+    pub line: u32,
+    pub message: String,
+}
+
+fn read_u8(buf: &mut &[u8]) -> Result<u8, Error> {
+    let (&b, rest) = buf.split_first().ok_or(Error::InvalidRecord)?;
+    *buf = rest;
+    Ok(b)
+}
+
+fn read_len(buf: &mut &[u8]) -> Result<LogValueLength, Error> {
+    let size = mem::size_of::<LogValueLength>();
+    if buf.len() < size {
+        return Err(Error::InvalidRecord);
+    }
+    let mut bytes = [0u8; mem::size_of::<LogValueLength>()];
+    bytes.copy_from_slice(&buf[..size]);
+    *buf = &buf[size..];
+    Ok(LogValueLength::from_ne_bytes(bytes))
+}
+
+fn read_value<'a>(buf: &mut &'a [u8]) -> Result<&'a [u8], Error> {
+    let len = usize::from(read_len(buf)?);
+    if buf.len() < len {
+        return Err(Error::InvalidRecord);
+    }
+    let (value, rest) = buf.split_at(len);
+    *buf = rest;
+    Ok(value)
+}
+
+fn read_str(buf: &[u8]) -> Result<String, Error> {
+    str::from_utf8(buf)
+        .map(str::to_owned)
+        .map_err(Error::InvalidString)
+}
+
+/// Renders the raw bytes of a single argument according to its `ArgType`
+/// and the requested `DisplayHint`, rejecting combinations that don't make
+/// sense (e.g. `:ipv4` on a `u64`).
+fn format_value(arg_type: ArgType, hint: DisplayHint, value: &[u8]) -> Result<String, Error> {
+    let mismatch = || Error::InvalidDisplayHint { hint, arg_type };
+
+    macro_rules! int_arg {
+        ($ty:ty) => {{
+            let bytes: [u8; mem::size_of::<$ty>()] =
+                value.try_into().map_err(|_| Error::InvalidRecord)?;
+            let v = <$ty>::from_ne_bytes(bytes);
+            match hint {
+                DisplayHint::Default => Ok(v.to_string()),
+                DisplayHint::LowerHex => Ok(format!("{v:x}")),
+                DisplayHint::UpperHex => Ok(format!("{v:X}")),
+                _ => Err(mismatch()),
+            }
+        }};
+    }
+
+    match arg_type {
+        ArgType::I8 => int_arg!(i8),
+        ArgType::I16 => int_arg!(i16),
+        ArgType::I32 => int_arg!(i32),
+        ArgType::I64 => int_arg!(i64),
+        ArgType::Isize => int_arg!(isize),
+        ArgType::U8 => int_arg!(u8),
+        ArgType::U16 => int_arg!(u16),
+        ArgType::U32 => match hint {
+            DisplayHint::IPv4 => {
+                let bytes: [u8; 4] = value.try_into().map_err(|_| Error::InvalidRecord)?;
+                Ok(Ipv4Addr::from(bytes).to_string())
+            }
+            _ => int_arg!(u32),
+        },
+        ArgType::U64 => int_arg!(u64),
+        ArgType::Usize => int_arg!(usize),
+        ArgType::F32 => {
+            let bytes: [u8; 4] = value.try_into().map_err(|_| Error::InvalidRecord)?;
+            match hint {
+                DisplayHint::Default => Ok(f32::from_ne_bytes(bytes).to_string()),
+                _ => Err(mismatch()),
+            }
+        }
+        ArgType::F64 => {
+            let bytes: [u8; 8] = value.try_into().map_err(|_| Error::InvalidRecord)?;
+            match hint {
+                DisplayHint::Default => Ok(f64::from_ne_bytes(bytes).to_string()),
+                _ => Err(mismatch()),
+            }
+        }
+        ArgType::ArrU8Len16 => match hint {
+            DisplayHint::IPv6 => {
+                let bytes: [u8; 16] = value.try_into().map_err(|_| Error::InvalidRecord)?;
+                Ok(Ipv6Addr::from(bytes).to_string())
+            }
+            _ => Err(mismatch()),
+        },
+        ArgType::ArrU16Len8 => Err(mismatch()),
+        ArgType::ArrU8Len6 => match hint {
+            DisplayHint::LowerMac => {
+                let bytes: [u8; 6] = value.try_into().map_err(|_| Error::InvalidRecord)?;
+                Ok(format_mac(&bytes, false))
+            }
+            DisplayHint::UpperMac => {
+                let bytes: [u8; 6] = value.try_into().map_err(|_| Error::InvalidRecord)?;
+                Ok(format_mac(&bytes, true))
+            }
+            _ => Err(mismatch()),
+        },
+        ArgType::Str => match hint {
+            DisplayHint::Default => read_str(value),
+            _ => Err(mismatch()),
+        },
+        ArgType::Bytes => match hint {
+            DisplayHint::Default => Ok(format!("{value:?}")),
+            DisplayHint::LowerHex => Ok(format_hex(value, false, " ")),
+            DisplayHint::UpperHex => Ok(format_hex(value, true, " ")),
+            _ => Err(mismatch()),
+        },
+    }
+}
+
+fn format_mac(octets: &[u8], upper: bool) -> String {
+    format_hex(octets, upper, ":")
+}
+
+fn format_hex(bytes: &[u8], upper: bool, sep: &str) -> String {
+    bytes
+        .iter()
+        .map(|b| {
+            if upper {
+                format!("{b:02X}")
+            } else {
+                format!("{b:02x}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(sep)
+}
+
+/// Decodes a single record from `buf`, returning the `Record` and the
+/// number of bytes consumed.
+pub fn parse(mut buf: &[u8]) -> Result<(Record, usize), Error> {
+    let start_len = buf.len();
+
+    let mut target = None;
+    let mut level = None;
+    let mut module = None;
+    let mut file = None;
+    let mut line = None;
+    let mut num_args = None;
+
+    for _ in 0..aya_log_common::LOG_FIELDS {
+        let tag = RecordField::try_from(read_u8(&mut buf)?).map_err(|()| Error::UnknownTag)?;
+        let _hint = DisplayHint::try_from(read_u8(&mut buf)?).map_err(|()| Error::UnknownTag)?;
+        let value = read_value(&mut buf)?;
+
+        match tag {
+            RecordField::Target => target = Some(read_str(value)?),
+            RecordField::Level => {
+                let &[raw] = value else {
+                    return Err(Error::InvalidRecord);
+                };
+                level = Some(Level::try_from(raw).map_err(|()| Error::UnknownTag)?);
+            }
+            RecordField::Module => module = Some(read_str(value)?),
+            RecordField::File => file = Some(read_str(value)?),
+            RecordField::Line => {
+                line = Some(u32::from_ne_bytes(
+                    value.try_into().map_err(|_| Error::InvalidRecord)?,
+                ));
+            }
+            RecordField::NumArgs => {
+                num_args = Some(usize::from_ne_bytes(
+                    value.try_into().map_err(|_| Error::InvalidRecord)?,
+                ));
+            }
+        }
+    }
+
+    let mut message = String::new();
+    for _ in 0..num_args.ok_or(Error::InvalidRecord)? {
+        let arg_type = ArgType::try_from(read_u8(&mut buf)?).map_err(|()| Error::UnknownTag)?;
+        let hint = DisplayHint::try_from(read_u8(&mut buf)?).map_err(|()| Error::UnknownTag)?;
+        let value = read_value(&mut buf)?;
+        message.push_str(&format_value(arg_type, hint, value)?);
+    }
+
+    let record = Record {
+        target: target.ok_or(Error::InvalidRecord)?,
+        level: level.ok_or(Error::InvalidRecord)?,
+        module: module.ok_or(Error::InvalidRecord)?,
+        file: file.ok_or(Error::InvalidRecord)?,
+        line: line.ok_or(Error::InvalidRecord)?,
+        message,
+    };
+
+    Ok((record, start_len - buf.len()))
+}